@@ -53,6 +53,7 @@ implemented for all FromStr types) to automatically convert the arguments.
  */
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::io::Write;
 use std::str::FromStr;
 
@@ -62,6 +63,28 @@ pub enum ArgError {
 	OutOfArgs,
 }
 
+/// the shells for which `Arguments::generate_completion` knows how to emit a
+/// completion script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+	Bash,
+	Zsh,
+	Fish,
+}
+
+/// escapes a string for safe inclusion inside a shell single-quoted literal: a
+/// `'` is closed, escaped and reopened (`'\''`).
+fn shell_single_quote_escape(s: &str) -> String {
+	s.replace('\'', "'\\''")
+}
+
+/// escapes a description for a zsh `_describe` spec, where a bare `:` would be
+/// read as the separator between the value and its description, then makes the
+/// result safe for the surrounding single quotes.
+fn zsh_describe_escape(s: &str) -> String {
+	shell_single_quote_escape(&s.replace(':', "\\:"))
+}
+
 /// This trait is the one which has to be implemented by every argument passed
 /// to the Arguments struct.
 pub trait Filler {
@@ -69,6 +92,16 @@ pub trait Filler {
 	fn type_name(&self) -> &'static str {
 		"unknown"
 	}
+	/// extra detail appended after the type name in the usage line, such as the
+	/// set of allowed choices. `None` by default.
+	fn detail(&self) -> Option<String> {
+		None
+	}
+	/// whether the flag may appear more than once, accumulating each time. Used
+	/// to append `...` to the type name in usage output. `false` by default.
+	fn repeatable(&self) -> bool {
+		false
+	}
 }
 
 struct BooleanFlag<'a> {
@@ -102,6 +135,75 @@ impl<T: FromStr> Filler for &mut T {
 	}
 }
 
+struct ManyFiller<'a, T> {
+	target: &'a mut Vec<T>,
+}
+
+impl<'a, T: FromStr> Filler for ManyFiller<'a, T> {
+	fn fill(&mut self, s: &mut dyn Iterator<Item = &str>) -> Result<(), ArgError> {
+		use std::any::type_name;
+
+		let item = s.next().ok_or(ArgError::OutOfArgs)?;
+		let value = T::from_str(item)
+			.or_else(|_err| Err(ArgError::Err(format!("error parsing {}", type_name::<T>()))))?;
+		self.target.push(value);
+		Ok(())
+	}
+	fn type_name(&self) -> &'static str {
+		use std::any::type_name;
+		type_name::<T>()
+	}
+	fn repeatable(&self) -> bool {
+		true
+	}
+}
+
+struct CountFiller<'a> {
+	target: &'a mut usize,
+}
+
+impl<'a> Filler for CountFiller<'a> {
+	fn fill(&mut self, _s: &mut dyn Iterator<Item = &str>) -> Result<(), ArgError> {
+		*self.target += 1;
+		Ok(())
+	}
+	fn type_name(&self) -> &'static str {
+		"flag"
+	}
+	fn repeatable(&self) -> bool {
+		true
+	}
+}
+
+struct ChoicesFlag<'a> {
+	value: &'a mut String,
+	name: String,
+	allowed: Vec<String>,
+}
+
+impl<'a> Filler for ChoicesFlag<'a> {
+	fn fill(&mut self, s: &mut dyn Iterator<Item = &str>) -> Result<(), ArgError> {
+		let item = s.next().ok_or(ArgError::OutOfArgs)?;
+		if self.allowed.iter().any(|a| a == item) {
+			*self.value = item.to_owned();
+			Ok(())
+		} else {
+			Err(ArgError::Err(format!(
+				"invalid value '{}' for --{} (expected: {})",
+				item,
+				self.name,
+				self.allowed.join(", ")
+			)))
+		}
+	}
+	fn type_name(&self) -> &'static str {
+		"choice"
+	}
+	fn detail(&self) -> Option<String> {
+		Some(self.allowed.join(", "))
+	}
+}
+
 struct Flag<'a> {
 	description: String,
 	value: Box<dyn Filler + 'a>,
@@ -110,6 +212,9 @@ struct Flag<'a> {
 /// the main struct which is responsible for managing all argument parsing logic
 pub struct Arguments<'a> {
 	flags: HashMap<String, Flag<'a>>,
+	shorts: HashMap<char, String>,
+	required: HashSet<String>,
+	error_code: i32,
 	name: Option<String>,
 }
 
@@ -119,6 +224,9 @@ impl<'a> Arguments<'a> {
 	pub fn new(name: Option<&str>) -> Self {
 		Arguments {
 			flags: HashMap::new(),
+			shorts: HashMap::new(),
+			required: HashSet::new(),
+			error_code: 2,
 			name: name.map(|v| v.to_owned()),
 		}
 	}
@@ -138,36 +246,282 @@ impl<'a> Arguments<'a> {
 		self.flags.insert(name.to_string(), new_flag);
 	}
 
+	/// adds a repeatable flag that parses one `FromStr` value per occurrence and
+	/// pushes it onto `target`, so `--include a --include b` yields
+	/// `vec!["a", "b"]`.
+	pub fn add_many<T, S>(&mut self, target: &'a mut Vec<T>, name: S, description: &str)
+	where
+		T: FromStr + 'a,
+		S: ToString,
+	{
+		let filler = ManyFiller { target };
+		let flag = Flag {
+			description: description.to_owned(),
+			value: Box::new(filler),
+		};
+		self.flags.insert(name.to_string(), flag);
+	}
+
+	/// adds a value-less flag whose counter increments once per occurrence, for
+	/// `-v -v -v`-style verbosity counting.
+	pub fn add_count<S: ToString>(&mut self, target: &'a mut usize, name: S, description: &str) {
+		let filler = CountFiller { target };
+		let flag = Flag {
+			description: description.to_owned(),
+			value: Box::new(filler),
+		};
+		self.flags.insert(name.to_string(), flag);
+	}
+
+	/// like `add_count`, but also registers a single-character short alias, so
+	/// the usual `-v -v -v` (or clustered `-vvv`) verbosity counting works.
+	pub fn add_count_short<S: ToString>(
+		&mut self,
+		target: &'a mut usize,
+		name: S,
+		short: char,
+		description: &str,
+	) {
+		let name = name.to_string();
+		self.shorts.insert(short, name.clone());
+		self.add_count(target, name, description);
+	}
+
+	/// adds a flag that only accepts one of the strings in `allowed`, assigning
+	/// it into `value`. Any other token fails parsing with an error listing the
+	/// valid choices, and the choices are surfaced in `usage`.
+	pub fn add_choices<S: ToString>(
+		&mut self,
+		value: &'a mut String,
+		name: S,
+		description: &str,
+		allowed: &[&str],
+	) {
+		let name = name.to_string();
+		let filler = ChoicesFlag {
+			value,
+			name: name.clone(),
+			allowed: allowed.iter().map(|s| s.to_string()).collect(),
+		};
+		let flag = Flag {
+			description: description.to_owned(),
+			value: Box::new(filler),
+		};
+		self.flags.insert(name, flag);
+	}
+
+	/// like `add`, but marks the flag as required: `parse` will fail, naming
+	/// every required flag that never appeared in the input.
+	pub fn add_required<T, S>(&mut self, filler: T, name: S, description: &str)
+	where
+		T: Filler + 'a,
+		S: ToString,
+	{
+		let name = name.to_string();
+		self.required.insert(name.clone());
+		self.add(filler, name, description);
+	}
+
+	/// like `add`, but also registers a single-character short alias (`-x`) for
+	/// the flag alongside its long name. Short boolean flags can be clustered
+	/// together as `-abc`.
+	pub fn add_short<T, S>(&mut self, filler: T, name: S, short: char, description: &str)
+	where
+		T: Filler + 'a,
+		S: ToString,
+	{
+		let name = name.to_string();
+		self.shorts.insert(short, name.clone());
+		self.add(filler, name, description);
+	}
+
+	/// like `add_bool`, but also registers a single-character short alias. Since
+	/// boolean flags report a `"flag"` type, they can be clustered together as
+	/// `-abc` (as can count flags, which report the same type).
+	pub fn add_bool_short<S: ToString>(
+		&mut self,
+		b: &'a mut bool,
+		name: S,
+		short: char,
+		description: &str,
+	) {
+		let name = name.to_string();
+		self.shorts.insert(short, name.clone());
+		self.add_bool(b, name, description);
+	}
+
 	/// fills every argument with the given arguments and returns a vector of
 	/// all the arguments that weren't taken by any flag. If it fails, returns a
 	/// string describing a parsing error or a lack of remaining arguments
 	pub fn parse<S: AsRef<str>>(&mut self, arguments: &[S]) -> Result<Vec<String>, String> {
-		let mut flags: Vec<&str> = Vec::new();
+		let mut flags: Vec<(String, Option<String>)> = Vec::new();
 		let mut values: Vec<&str> = Vec::new();
+		let mut end_of_options = false;
 
 		for a in arguments.iter().map(|s| s.as_ref()) {
-			if a.starts_with("--") {
-				flags.push(&a[2..]);
+			if end_of_options {
+				values.push(a);
+			} else if a == "--" {
+				end_of_options = true;
+			} else if a.starts_with("--") {
+				let body = &a[2..];
+				match body.split_once('=') {
+					Some((name, value)) => {
+						flags.push((name.to_owned(), Some(value.to_owned())))
+					}
+					None => flags.push((body.to_owned(), None)),
+				}
+			} else if a.starts_with('-') && a.len() > 1 && self.is_short_cluster(&a[1..]) {
+				flags.extend(self.expand_short(&a[1..])?.into_iter().map(|n| (n, None)));
 			} else {
 				values.push(a);
 			}
 		}
 
 		let mut values_iter = values.into_iter();
-		for f in flags.into_iter() {
-			let mut flag = self
+		let mut seen: HashSet<String> = HashSet::new();
+		for (f, inline) in flags.into_iter() {
+			let name = self.resolve_flag(&f)?;
+			let flag = self
 				.flags
-				.get_mut(f)
+				.get_mut(&name)
 				.ok_or_else(|| format!("invalid flag: {}", &f))?;
+			seen.insert(name.clone());
+
+			match inline {
+				Some(value) => {
+					if flag.value.type_name() == "flag" {
+						return Err(format!("{}: flag does not take a value", f));
+					}
+					let mut single = std::iter::once(value.as_str());
+					flag.value
+						.fill(&mut single)
+						.or_else(|err| Err(format!("{}: {:?}", f, err)))?;
+				}
+				None => {
+					flag.value
+						.fill(&mut values_iter)
+						.or_else(|err| Err(format!("{}: {:?}", f, err)))?;
+				}
+			}
+		}
 
-			flag.value
-				.fill(&mut values_iter)
-				.or_else(|err| Err(format!("{}: {:?}", f, err)))?;
+		let mut missing: Vec<&String> = self.required.difference(&seen).collect();
+		if !missing.is_empty() {
+			missing.sort();
+			let names: Vec<String> = missing.iter().map(|n| format!("--{}", n)).collect();
+			return Err(format!("missing required flag(s): {}", names.join(", ")));
 		}
 
 		Ok(values_iter.map(|s| s.to_owned()).collect())
 	}
 
+	/// parses the given arguments, but on error prints the error and the usage
+	/// string to stderr and exits the process with the error code set by
+	/// `set_error_code` (default 2) instead of returning the error.
+	pub fn parse_or_exit<S: AsRef<str>>(&mut self, arguments: &[S]) -> Vec<String> {
+		let usage = self.usage();
+		let code = self.error_code;
+		match self.parse(arguments) {
+			Ok(rest) => rest,
+			Err(e) => {
+				eprintln!("{}", e);
+				eprint!("{}", usage);
+				std::process::exit(code);
+			}
+		}
+	}
+
+	/// sets the exit code used by `parse_or_exit` when argument parsing fails.
+	/// The default is 2, matching the GNU convention for usage errors.
+	pub fn set_error_code(&mut self, code: i32) {
+		self.error_code = code;
+	}
+
+	/// whether a token following a single `-` should be treated as a short-flag
+	/// cluster: it must lead with a registered short char. This keeps negative
+	/// numbers and other `-`-leading values (`-5`) from being mistaken for flags.
+	fn is_short_cluster(&self, cluster: &str) -> bool {
+		cluster
+			.chars()
+			.next()
+			.map(|c| self.shorts.contains_key(&c))
+			.unwrap_or(false)
+	}
+
+	/// expands a short-flag cluster (the token without its leading `-`) into the
+	/// long names it stands for. A lone short flag resolves through the short
+	/// alias map; a multi-character cluster like `-abc` is only valid when every
+	/// char but the last names a value-less flag (one whose type is `"flag"`,
+	/// i.e. a boolean or count flag), since a value-consuming flag can only sit
+	/// at the end of a cluster.
+	fn expand_short(&self, cluster: &str) -> Result<Vec<String>, String> {
+		let chars: Vec<char> = cluster.chars().collect();
+		let mut names = Vec::with_capacity(chars.len());
+		let last = chars.len() - 1;
+
+		for (i, c) in chars.into_iter().enumerate() {
+			let name = self
+				.shorts
+				.get(&c)
+				.ok_or_else(|| format!("invalid flag: -{}", c))?;
+
+			if i != last {
+				let is_flag = self
+					.flags
+					.get(name)
+					.map(|fl| fl.value.type_name() == "flag")
+					.unwrap_or(false);
+				if !is_flag {
+					return Err(format!(
+						"cannot cluster non-boolean flag -{} ({}) before the end of -{}",
+						c, name, cluster
+					));
+				}
+			}
+
+			names.push(name.clone());
+		}
+
+		Ok(names)
+	}
+
+	/// resolves a flag token (without the leading `--`) to a registered flag
+	/// name. An exact match wins immediately; otherwise we fall back to
+	/// unambiguous prefix matching like GNU `getopt_long`, so `--num` resolves
+	/// to `--number`. An ambiguous prefix lists the candidates and an unknown
+	/// one keeps the usual "invalid flag" error.
+	fn resolve_flag(&self, token: &str) -> Result<String, String> {
+		if token.is_empty() {
+			return Err("invalid flag: ".to_owned());
+		}
+
+		if self.flags.contains_key(token) {
+			return Ok(token.to_owned());
+		}
+
+		let mut matches: Vec<&String> = self
+			.flags
+			.keys()
+			.filter(|name| name.starts_with(token))
+			.collect();
+
+		match matches.len() {
+			0 => Err(format!("invalid flag: {}", token)),
+			1 => Ok(matches.remove(0).to_owned()),
+			_ => {
+				matches.sort();
+				let candidates: Vec<String> =
+					matches.iter().map(|name| format!("--{}", name)).collect();
+				Err(format!(
+					"ambiguous flag '{}': matches {}",
+					token,
+					candidates.join(", ")
+				))
+			}
+		}
+	}
+
 	/// generates a usage string
 	pub fn usage(&self) -> String {
 		let mut o = String::new();
@@ -180,10 +534,17 @@ impl<'a> Arguments<'a> {
 			o.push_str(&format!("usage:\n{} [flags] args...\n", exec));
 		}
 		for i in flags {
+			let mut type_name = match i.1.value.detail() {
+				Some(detail) => format!("{}: {}", i.1.value.type_name(), detail),
+				None => i.1.value.type_name().to_owned(),
+			};
+			if i.1.value.repeatable() {
+				type_name.push_str("...");
+			}
 			o.push_str(&format!(
 				"\t--{: <20} ({}) {}\n",
 				i.0,
-				i.1.value.type_name(),
+				type_name,
 				i.1.description,
 				//width = max_len + 4
 			));
@@ -191,6 +552,52 @@ impl<'a> Arguments<'a> {
 		o
 	}
 
+	/// generates a tab-completion script for the given shell based on the
+	/// registered flags. The script is keyed on the executable name given to
+	/// `new`; if no name was given, `prog` is used as a placeholder.
+	pub fn generate_completion(&self, shell: Shell) -> String {
+		let exec = self.name.clone().unwrap_or_else(|| "prog".to_owned());
+
+		let mut flags: Vec<_> = self.flags.iter().collect();
+		flags.sort_by_key(|(name, _)| name.to_owned());
+
+		match shell {
+			Shell::Bash => {
+				let words: Vec<String> =
+					flags.iter().map(|(name, _)| format!("--{}", name)).collect();
+				format!("complete -W \"{}\" {}\n", words.join(" "), exec)
+			}
+			Shell::Zsh => {
+				let mut o = String::new();
+				o.push_str(&format!("#compdef {}\n", exec));
+				o.push_str("local -a _flags\n");
+				o.push_str("_flags=(\n");
+				for (name, fl) in &flags {
+					o.push_str(&format!(
+						"\t'--{}:{}'\n",
+						shell_single_quote_escape(name),
+						zsh_describe_escape(&fl.description)
+					));
+				}
+				o.push_str(")\n");
+				o.push_str("_describe 'flag' _flags\n");
+				o
+			}
+			Shell::Fish => {
+				let mut o = String::new();
+				for (name, fl) in &flags {
+					o.push_str(&format!(
+						"complete -c {} -l {} -d '{}'\n",
+						exec,
+						name,
+						shell_single_quote_escape(&fl.description)
+					));
+				}
+				o
+			}
+		}
+	}
+
 	/// since the default implementation of Filller for &mut bool would require
 	/// the user tu write `./program --boolean-flag true` instead of just
 	/// `./program --boolean-flag`, this functions adds a flag that, when given,
@@ -205,12 +612,192 @@ impl<'a> Arguments<'a> {
 	}
 }
 
+/// describes a single leaf flag of a combinator `Parser` tree. A `meta` walk
+/// collects these so the applicative API can regenerate the same usage output
+/// as `Arguments::usage`.
+#[derive(Debug, Clone)]
+pub struct FlagMeta {
+	pub name: String,
+	pub description: String,
+	pub type_name: &'static str,
+	pub is_switch: bool,
+}
+
+/// the flags a `Parser` extracted from the raw argument list before evaluating
+/// the tree: value-carrying flags and the set of switches that were present.
+struct ParsedArgs {
+	values: HashMap<String, String>,
+	switches: HashSet<String>,
+}
+
+/// An applicative, owned-result alternative to `Arguments`. A `Parser<T>` is
+/// built from primitive parsers (`flag`, `switch`) combined with `zip` and
+/// `map`, and `run` evaluates the whole tree against the arguments and returns
+/// a `T` by value, so there is no `&mut` borrow dance and no `drop` before
+/// reading the results.
+pub struct Parser<T> {
+	meta: Vec<FlagMeta>,
+	run: Box<dyn Fn(&ParsedArgs) -> Result<T, String>>,
+}
+
+impl<T: 'static> Parser<T> {
+	/// transforms the parsed value with `f`, leaving the flag metadata intact.
+	pub fn map<U, F>(self, f: F) -> Parser<U>
+	where
+		U: 'static,
+		F: Fn(T) -> U + 'static,
+	{
+		let run = self.run;
+		Parser {
+			meta: self.meta,
+			run: Box::new(move |parsed| run(parsed).map(&f)),
+		}
+	}
+
+	/// collects the metadata of every leaf in the tree.
+	pub fn meta(&self) -> Vec<FlagMeta> {
+		self.meta.clone()
+	}
+
+	/// regenerates the same usage output as `Arguments::usage` from the leaf
+	/// metadata, keyed on the given executable name.
+	pub fn usage(&self, name: Option<&str>) -> String {
+		let mut o = String::new();
+		let mut meta = self.meta.clone();
+		meta.sort_by(|a, b| a.name.cmp(&b.name));
+
+		if let Some(exec) = name {
+			o.push_str(&format!("usage:\n{} [flags] args...\n", exec));
+		}
+		for m in meta {
+			let type_name = if m.is_switch { "flag" } else { m.type_name };
+			o.push_str(&format!(
+				"\t--{: <20} ({}) {}\n",
+				m.name, type_name, m.description,
+			));
+		}
+		o
+	}
+
+	/// evaluates the tree against the given arguments, returning the fully
+	/// constructed value or a parse error string.
+	pub fn run<S: AsRef<str>>(&self, args: &[S]) -> Result<T, String> {
+		let parsed = self.tokenize(args)?;
+		(self.run)(&parsed)
+	}
+
+	/// splits the raw arguments into value flags and present switches, using the
+	/// leaf metadata to know which flags consume a following value.
+	fn tokenize<S: AsRef<str>>(&self, args: &[S]) -> Result<ParsedArgs, String> {
+		let switches: HashSet<&str> = self
+			.meta
+			.iter()
+			.filter(|m| m.is_switch)
+			.map(|m| m.name.as_str())
+			.collect();
+		let known: HashSet<&str> = self.meta.iter().map(|m| m.name.as_str()).collect();
+
+		let mut values = HashMap::new();
+		let mut present = HashSet::new();
+
+		let mut iter = args.iter().map(|s| s.as_ref());
+		while let Some(a) = iter.next() {
+			let body = match a.strip_prefix("--") {
+				Some(body) => body,
+				None => continue,
+			};
+
+			let (name, inline) = match body.split_once('=') {
+				Some((name, value)) => (name, Some(value.to_owned())),
+				None => (body, None),
+			};
+
+			if !known.contains(name) {
+				return Err(format!("invalid flag: {}", name));
+			}
+
+			if switches.contains(name) {
+				if inline.is_some() {
+					return Err(format!("{}: flag does not take a value", name));
+				}
+				present.insert(name.to_owned());
+			} else {
+				let value = match inline {
+					Some(value) => value,
+					None => iter
+						.next()
+						.ok_or_else(|| format!("{}: missing value", name))?
+						.to_owned(),
+				};
+				values.insert(name.to_owned(), value);
+			}
+		}
+
+		Ok(ParsedArgs {
+			values,
+			switches: present,
+		})
+	}
+}
+
+/// a primitive parser for a value-carrying flag converted through `FromStr`.
+pub fn flag<T: FromStr + 'static>(name: &str, description: &str) -> Parser<T> {
+	use std::any::type_name;
+
+	let name = name.to_owned();
+	let lookup = name.clone();
+	Parser {
+		meta: vec![FlagMeta {
+			name,
+			description: description.to_owned(),
+			type_name: type_name::<T>(),
+			is_switch: false,
+		}],
+		run: Box::new(move |parsed| {
+			let raw = parsed
+				.values
+				.get(&lookup)
+				.ok_or_else(|| format!("{}: missing value", lookup))?;
+			T::from_str(raw).or_else(|_| {
+				Err(format!("error parsing {}", type_name::<T>()))
+			})
+		}),
+	}
+}
+
+/// a primitive parser for a boolean switch: `true` when the flag is present.
+pub fn switch(name: &str, description: &str) -> Parser<bool> {
+	let name = name.to_owned();
+	let lookup = name.clone();
+	Parser {
+		meta: vec![FlagMeta {
+			name,
+			description: description.to_owned(),
+			type_name: "flag",
+			is_switch: true,
+		}],
+		run: Box::new(move |parsed| Ok(parsed.switches.contains(&lookup))),
+	}
+}
+
+/// combines two parsers into one that yields both of their results as a tuple.
+pub fn zip<A: 'static, B: 'static>(a: Parser<A>, b: Parser<B>) -> Parser<(A, B)> {
+	let mut meta = a.meta.clone();
+	meta.extend(b.meta.clone());
+	let run_a = a.run;
+	let run_b = b.run;
+	Parser {
+		meta,
+		run: Box::new(move |parsed| Ok((run_a(parsed)?, run_b(parsed)?))),
+	}
+}
+
 #[test]
 fn simple_test() {
 	let mut number: usize = 12;
 	let mut string: String = String::new();
 	let mut boolean: bool = false;
-	let mut arguments = Arguments::new();
+	let mut arguments = Arguments::new(None);
 
 	let a = &["--bool", "true", "--number", "123", "--string", "penis"];
 
@@ -224,3 +811,176 @@ fn simple_test() {
 	assert_eq!(boolean, true);
 	assert_eq!(string, "penis");
 }
+
+#[test]
+fn completion_escapes_descriptions() {
+	let mut flag = false;
+	let mut arguments = Arguments::new(Some("prog"));
+	arguments.add_bool(&mut flag, "mode", "set it's value (a:b)");
+
+	let zsh = arguments.generate_completion(Shell::Zsh);
+	assert!(zsh.contains("'--mode:set it'\\''s value (a\\:b)'"));
+
+	let fish = arguments.generate_completion(Shell::Fish);
+	assert!(fish.contains("-d 'set it'\\''s value (a:b)'"));
+
+	let bash = arguments.generate_completion(Shell::Bash);
+	assert_eq!(bash, "complete -W \"--mode\" prog\n");
+}
+
+#[test]
+fn prefix_abbreviation() {
+	let mut number: usize = 0;
+	let mut status: String = String::new();
+	let mut string: String = String::new();
+	let mut arguments = Arguments::new(None);
+
+	arguments.add(&mut number, "number", "a number");
+	arguments.add(&mut status, "status", "a status");
+	arguments.add(&mut string, "string", "a string");
+
+	// unambiguous prefix resolves
+	arguments.parse(&["--num", "5"]).unwrap();
+	// ambiguous prefix lists candidates
+	let err = arguments.parse(&["--st", "x"]).unwrap_err();
+	assert_eq!(err, "ambiguous flag 'st': matches --status, --string");
+	drop(arguments);
+
+	assert_eq!(number, 5);
+}
+
+#[test]
+fn double_dash_ends_options() {
+	let mut string: String = String::new();
+	let mut arguments = Arguments::new(None);
+	arguments.add(&mut string, "string", "a string");
+
+	// a bare `--` is not a flag; everything after it is positional
+	let rest = arguments.parse(&["--string", "a", "--", "-5", "--string"]).unwrap();
+	drop(arguments);
+
+	assert_eq!(string, "a");
+	assert_eq!(rest, vec!["-5".to_owned(), "--string".to_owned()]);
+}
+
+#[test]
+fn short_flags_and_clustering() {
+	let mut a = false;
+	let mut b = false;
+	let mut verbose: usize = 0;
+	let mut string = String::new();
+	let mut arguments = Arguments::new(None);
+
+	arguments.add_bool_short(&mut a, "aa", 'a', "flag a");
+	arguments.add_bool_short(&mut b, "bb", 'b', "flag b");
+	arguments.add_count_short(&mut verbose, "verbose", 'v', "verbosity");
+	arguments.add_short(&mut string, "string", 's', "a string");
+
+	// clustered booleans and counts expand, value flag sits at the end
+	let rest = arguments.parse(&["-abvv", "-s", "x", "-5"]).unwrap();
+	drop(arguments);
+
+	assert_eq!(a, true);
+	assert_eq!(b, true);
+	assert_eq!(verbose, 2);
+	assert_eq!(string, "x");
+	// `-5` is not a registered short, so it stays a positional value
+	assert_eq!(rest, vec!["-5".to_owned()]);
+}
+
+#[test]
+fn inline_value_syntax() {
+	let mut number: usize = 0;
+	let mut help = false;
+	let mut arguments = Arguments::new(None);
+	arguments.add(&mut number, "number", "a number");
+	arguments.add_bool(&mut help, "help", "help");
+
+	arguments.parse(&["--number=42"]).unwrap();
+	// a boolean flag rejects an attached value
+	let err = arguments.parse(&["--help=true"]).unwrap_err();
+	assert_eq!(err, "help: flag does not take a value");
+	drop(arguments);
+
+	assert_eq!(number, 42);
+}
+
+#[test]
+fn required_flags() {
+	let mut number: usize = 0;
+	let mut string = String::new();
+	let mut arguments = Arguments::new(None);
+	arguments.add_required(&mut number, "number", "a number");
+	arguments.add_required(&mut string, "string", "a string");
+
+	// both missing are reported, sorted
+	let err = arguments.parse(&[] as &[&str]).unwrap_err();
+	assert_eq!(err, "missing required flag(s): --number, --string");
+
+	// providing them succeeds
+	arguments.parse(&["--number", "1", "--string", "s"]).unwrap();
+	drop(arguments);
+
+	assert_eq!(number, 1);
+	assert_eq!(string, "s");
+}
+
+#[test]
+fn choices_flag() {
+	let mut mode = String::new();
+	let mut arguments = Arguments::new(None);
+	arguments.add_choices(&mut mode, "mode", "the mode", &["a", "b", "c"]);
+
+	// a disallowed value is rejected with the list of choices
+	let err = arguments.parse(&["--mode", "x"]).unwrap_err();
+	assert_eq!(err, "mode: Err(\"invalid value 'x' for --mode (expected: a, b, c)\")");
+
+	// and the choices surface in the usage line
+	assert!(arguments.usage().contains("(choice: a, b, c)"));
+
+	arguments.parse(&["--mode", "b"]).unwrap();
+	drop(arguments);
+
+	assert_eq!(mode, "b");
+}
+
+#[test]
+fn parser_combinator() {
+	let p = zip(
+		flag::<usize>("number", "a number"),
+		switch("help", "displays help"),
+	)
+	.map(|(n, h)| (n * 2, h));
+
+	let (doubled, help) = p.run(&["--number", "21", "--help"]).unwrap();
+	assert_eq!(doubled, 42);
+	assert_eq!(help, true);
+
+	// inline value form works too, and a missing value flag errors
+	assert_eq!(p.run(&["--number=5"]).unwrap(), (10, false));
+	let err = p.run(&["--help"]).unwrap_err();
+	assert_eq!(err, "number: missing value");
+
+	// the meta walk regenerates the usage output
+	assert!(p.usage(Some("prog")).contains("--number"));
+}
+
+#[test]
+fn repeatable_and_count_flags() {
+	let mut includes: Vec<String> = Vec::new();
+	let mut verbose: usize = 0;
+	let mut arguments = Arguments::new(None);
+	arguments.add_many(&mut includes, "include", "files to include");
+	arguments.add_count(&mut verbose, "verbose", "verbosity");
+
+	arguments
+		.parse(&["--include", "a", "--include", "b", "--verbose", "--verbose", "--verbose"])
+		.unwrap();
+
+	// the repeatable nature is reflected in usage with a trailing `...`
+	assert!(arguments.usage().contains("..."));
+	drop(arguments);
+
+	assert_eq!(includes, vec!["a".to_owned(), "b".to_owned()]);
+	assert_eq!(verbose, 3);
+}